@@ -0,0 +1,48 @@
+use crate::config::xml_ext::{XmlElement, XmlNode};
+use anyhow::*;
+
+/// A single use of a widget within a window or another widget's body: the raw
+/// (possibly `::`-qualified) name it was written with, plus its own nested uses.
+///
+/// The name is kept as written; the only change made to it is the rewrite of an
+/// included library's internal `::`-qualified references onto their new
+/// namespace, done by `EwwConfig::merge_included_config`. It's checked against the
+/// widget registry by `EwwConfig::from_xml_element`'s post-merge validation pass,
+/// so this type intentionally carries no namespace of its own.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WidgetUse {
+    pub name: String,
+    pub children: Vec<WidgetUse>,
+}
+
+impl WidgetUse {
+    pub fn from_xml_node(xml: XmlNode) -> Result<Self> {
+        Self::from_xml_element(xml.as_element()?)
+    }
+
+    /// An unqualified widget use is just written as its own tag, e.g. `<clock/>`.
+    /// A `::`-qualified name can't be, since XML tag names can't contain `::`
+    /// (and a single `:` is reserved for namespace prefixes by the XML spec) — so
+    /// those are instead written as `<use name="weather::forecast"/>`.
+    pub fn from_xml_element(xml: XmlElement) -> Result<Self> {
+        let name = if xml.tag_name() == "use" { xml.attr("name")?.to_owned() } else { xml.tag_name().to_owned() };
+        let children = xml.child_elements().map(Self::from_xml_element).collect::<Result<Vec<_>>>()?;
+        Ok(WidgetUse { name, children })
+    }
+}
+
+/// A named, reusable widget, declared in a `<definitions>` block and referenced
+/// elsewhere by `WidgetUse::name`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WidgetDefinition {
+    pub name: String,
+    pub widget: WidgetUse,
+}
+
+impl WidgetDefinition {
+    pub fn from_xml_element(xml: XmlElement) -> Result<Self> {
+        let name = xml.attr("name")?.to_owned();
+        let widget = WidgetUse::from_xml_element(xml.only_child()?.as_element()?)?;
+        Ok(WidgetDefinition { name, widget })
+    }
+}
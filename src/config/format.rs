@@ -0,0 +1,274 @@
+use anyhow::*;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// The markup language a config file is written in, inferred from its extension.
+///
+/// XML configs are parsed directly by the existing `roxmltree`-based pipeline.
+/// YAML and TOML configs are first deserialized into a [`StructuredConfig`] and
+/// then lowered into the equivalent XML text, so `EwwConfig::from_xml_element`
+/// (and everything built on top of it) only ever has to understand one format.
+///
+/// Requires `serde_yaml` and `toml` as crate dependencies alongside the
+/// `serde`/`derive` features already pulled in for `WindowName`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Xml,
+    Yaml,
+    Toml,
+}
+
+impl ConfigFormat {
+    pub fn from_path(path: &Path) -> Result<Self> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("xml") => Ok(ConfigFormat::Xml),
+            Some("yaml") | Some("yml") => Ok(ConfigFormat::Yaml),
+            Some("toml") => Ok(ConfigFormat::Toml),
+            Some(other) => bail!("Unsupported config file extension: '{}'", other),
+            None => bail!("Config file has no extension, cannot determine its format: {}", path.display()),
+        }
+    }
+}
+
+/// A structure-agnostic widget/element node, deserialized from either YAML or TOML.
+///
+/// This is the common shape that widget-use trees (inside `definitions` and
+/// `windows`) are written in when a config isn't XML. It mirrors an XML element
+/// closely enough (`type` ~ tag name, `attrs` ~ attributes, `children` ~ child
+/// elements) that it can be losslessly re-serialized into XML text.
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(untagged)]
+pub enum StructuredNode {
+    Text(String),
+    Node {
+        #[serde(rename = "type")]
+        tag: String,
+        #[serde(default)]
+        attrs: HashMap<String, String>,
+        #[serde(default)]
+        children: Vec<StructuredNode>,
+    },
+}
+
+impl StructuredNode {
+    fn write_xml(&self, out: &mut String) {
+        match self {
+            StructuredNode::Text(text) => out.push_str(&xml_escape_text(text)),
+            StructuredNode::Node { tag, attrs, children } => {
+                out.push('<');
+                out.push_str(tag);
+                for (key, value) in attrs {
+                    out.push(' ');
+                    out.push_str(key);
+                    out.push_str("=\"");
+                    out.push_str(&xml_escape_attr(value));
+                    out.push('"');
+                }
+                if children.is_empty() {
+                    out.push_str("/>");
+                } else {
+                    out.push('>');
+                    for child in children {
+                        child.write_xml(out);
+                    }
+                    out.push_str("</");
+                    out.push_str(tag);
+                    out.push('>');
+                }
+            }
+        }
+    }
+}
+
+fn xml_escape_text(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+fn xml_escape_attr(text: &str) -> String {
+    xml_escape_text(text).replace('"', "&quot;")
+}
+
+/// The root shape every YAML/TOML config must follow. It mirrors the
+/// `<eww><definitions>..</definitions><windows>..</windows><variables>..</variables></eww>`
+/// XML layout key-for-key, just spelled as maps and lists instead of elements.
+///
+/// This is a subset of what XML configs can express: composition features that
+/// only make sense as markup directives (`<include>`, a `<definitions
+/// namespace="...">` block) have no structured-format equivalent, so multi-file
+/// YAML/TOML config trees aren't supported yet. A single structured file maps to
+/// an identical `EwwConfig` as the equivalent single XML file, but `<include>`
+/// must stay in XML.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct StructuredConfig {
+    #[serde(default)]
+    pub definitions: HashMap<String, StructuredNode>,
+    #[serde(default)]
+    pub windows: HashMap<String, StructuredWindow>,
+    #[serde(default)]
+    pub variables: Vec<StructuredVariable>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct StructuredWindow {
+    pub pos: (i32, i32),
+    pub size: (i32, i32),
+    pub widget: StructuredNode,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum StructuredVariable {
+    Var {
+        name: String,
+        value: String,
+    },
+    ScriptVar {
+        name: String,
+        /// Absent means tail mode, matching the XML `script-var` parser: no
+        /// `interval` attribute implies `type="tail"`.
+        #[serde(default)]
+        interval: Option<String>,
+        command: String,
+    },
+}
+
+/// Converts a parsed structured config into the XML text an equivalent `.xml`
+/// file would contain, so it can be fed through the existing parsing pipeline
+/// unchanged and produce an identical `EwwConfig`.
+pub fn structured_config_to_xml(config: &StructuredConfig) -> String {
+    let mut out = String::from("<eww><definitions>");
+    for (name, body) in &config.definitions {
+        out.push_str(&format!("<def name=\"{}\">", xml_escape_attr(name)));
+        body.write_xml(&mut out);
+        out.push_str("</def>");
+    }
+    out.push_str("</definitions><windows>");
+    for (name, window) in &config.windows {
+        out.push_str(&format!(
+            "<window name=\"{}\"><size x=\"{}\" y=\"{}\"/><pos x=\"{}\" y=\"{}\"/><widget>",
+            xml_escape_attr(name),
+            window.size.0,
+            window.size.1,
+            window.pos.0,
+            window.pos.1
+        ));
+        window.widget.write_xml(&mut out);
+        out.push_str("</widget></window>");
+    }
+    out.push_str("</windows><variables>");
+    for var in &config.variables {
+        match var {
+            StructuredVariable::Var { name, value } => {
+                out.push_str(&format!("<var name=\"{}\">{}</var>", xml_escape_attr(name), xml_escape_text(value)));
+            }
+            StructuredVariable::ScriptVar { name, interval, command } => {
+                let interval_attr = match interval {
+                    Some(interval) => format!(" interval=\"{}\"", xml_escape_attr(interval)),
+                    None => " type=\"tail\"".to_owned(),
+                };
+                out.push_str(&format!(
+                    "<script-var name=\"{}\"{}>{}</script-var>",
+                    xml_escape_attr(name),
+                    interval_attr,
+                    xml_escape_text(command)
+                ));
+            }
+        }
+    }
+    out.push_str("</variables></eww>");
+    out
+}
+
+/// Parses a YAML or TOML config's content into the common structured representation.
+///
+/// Both `serde_yaml` and `toml` report the line/column of a parse failure in their
+/// error's `Display` output, so that position information is preserved in the
+/// returned error even though it isn't routed through `roxmltree`.
+pub fn parse_structured(format: ConfigFormat, content: &str) -> Result<StructuredConfig> {
+    match format {
+        ConfigFormat::Yaml => serde_yaml::from_str(content).context("failed to parse YAML config"),
+        ConfigFormat::Toml => toml::from_str(content).context("failed to parse TOML config"),
+        ConfigFormat::Xml => unreachable!("XML configs are parsed directly, not through the structured bridge"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_path_dispatches_on_extension() {
+        assert_eq!(ConfigFormat::from_path(Path::new("eww.xml")).unwrap(), ConfigFormat::Xml);
+        assert_eq!(ConfigFormat::from_path(Path::new("eww.yaml")).unwrap(), ConfigFormat::Yaml);
+        assert_eq!(ConfigFormat::from_path(Path::new("eww.yml")).unwrap(), ConfigFormat::Yaml);
+        assert_eq!(ConfigFormat::from_path(Path::new("eww.toml")).unwrap(), ConfigFormat::Toml);
+        assert!(ConfigFormat::from_path(Path::new("eww.json")).is_err());
+        assert!(ConfigFormat::from_path(Path::new("eww")).is_err());
+    }
+
+    const YAML_CONFIG: &str = r#"
+windows:
+  main:
+    pos: [0, 0]
+    size: [100, 100]
+    widget:
+      type: label
+      attrs:
+        text: hello
+variables:
+  - kind: var
+    name: greeting
+    value: hi
+  - kind: script-var
+    name: polled
+    interval: 2s
+    command: echo 1
+  - kind: script-var
+    name: tailed
+    command: tail -f /tmp/foo
+"#;
+
+    const TOML_CONFIG: &str = r#"
+[windows.main]
+pos = [0, 0]
+size = [100, 100]
+[windows.main.widget]
+type = "label"
+[windows.main.widget.attrs]
+text = "hello"
+[[variables]]
+kind = "var"
+name = "greeting"
+value = "hi"
+[[variables]]
+kind = "script-var"
+name = "polled"
+interval = "2s"
+command = "echo 1"
+[[variables]]
+kind = "script-var"
+name = "tailed"
+command = "tail -f /tmp/foo"
+"#;
+
+    #[test]
+    fn yaml_and_toml_lower_to_equivalent_xml() {
+        let yaml = parse_structured(ConfigFormat::Yaml, YAML_CONFIG).unwrap();
+        let toml = parse_structured(ConfigFormat::Toml, TOML_CONFIG).unwrap();
+        assert_eq!(structured_config_to_xml(&yaml), structured_config_to_xml(&toml));
+    }
+
+    #[test]
+    fn structured_script_var_without_interval_emits_tail_type() {
+        let yaml = parse_structured(ConfigFormat::Yaml, YAML_CONFIG).unwrap();
+        let xml = structured_config_to_xml(&yaml);
+        assert!(xml.contains(r#"<script-var name="tailed" type="tail">"#));
+        assert!(xml.contains(r#"<script-var name="polled" interval="2s">"#));
+    }
+
+    #[test]
+    fn parse_structured_reports_errors() {
+        assert!(parse_structured(ConfigFormat::Yaml, "windows: [").is_err());
+        assert!(parse_structured(ConfigFormat::Toml, "windows = [").is_err());
+    }
+}
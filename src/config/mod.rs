@@ -3,12 +3,16 @@ use crate::value::PrimitiveValue;
 use crate::value::VarName;
 use anyhow::*;
 use element::*;
+use format::ConfigFormat;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::fmt;
+use std::path::{Path, PathBuf};
 use xml_ext::*;
 
 pub mod element;
+pub mod format;
 pub mod xml_ext;
 
 #[macro_export]
@@ -26,27 +30,244 @@ macro_rules! ensure_xml_tag_is {
     };
 }
 
+/// A source of dynamic variable state, either polled on a timer or tailed from a
+/// long-running process that prints a new value on every line of stdout.
 #[derive(Clone, Debug, PartialEq)]
-pub struct ScriptVar {
-    pub name: VarName,
-    pub command: String,
-    pub interval: std::time::Duration,
+pub enum ScriptVar {
+    Poll {
+        name: VarName,
+        command: String,
+        interval: std::time::Duration,
+    },
+    Tail {
+        name: VarName,
+        command: String,
+    },
 }
 
 impl ScriptVar {
+    pub fn name(&self) -> &VarName {
+        match self {
+            ScriptVar::Poll { name, .. } => name,
+            ScriptVar::Tail { name, .. } => name,
+        }
+    }
+
+    /// A `script-var` is tail mode when given `type="tail"`, or when it has no
+    /// `interval` attribute at all; otherwise (`type="poll"`, or a plain `interval`)
+    /// it's poll mode.
     pub fn from_xml_element(xml: XmlElement) -> Result<Self> {
         ensure_xml_tag_is!(xml, "script-var");
 
         let name = VarName(xml.attr("name")?.to_owned());
-        let interval = util::parse_duration(xml.attr("interval")?)?;
         let command = xml.only_child()?.as_text()?.text();
-        Ok(ScriptVar { name, interval, command })
+
+        let is_tail = match xml.attr("type").ok() {
+            Some("tail") => true,
+            Some("poll") => false,
+            Some(other) => bail!("{} | unknown script-var type: '{}'", xml.text_pos(), other),
+            None => xml.attr("interval").is_err(),
+        };
+
+        if is_tail {
+            Ok(ScriptVar::Tail { name, command })
+        } else {
+            let interval = util::parse_duration(xml.attr("interval")?)?;
+            Ok(ScriptVar::Poll { name, command, interval })
+        }
+    }
+
+    /// How many times in a row `spawn_tail`'s child is allowed to exit without ever
+    /// producing a line of output before the restart loop gives up. A command that's
+    /// merely flaky eventually emits something and resets this counter; a command
+    /// that's simply misconfigured (typo, one-shot, wrong shell) never does, and
+    /// without a cap the thread would restart it every `MAX_RESTART_BACKOFF` forever.
+    const MAX_EMPTY_RESTARTS: u32 = 10;
+    const MAX_RESTART_BACKOFF: std::time::Duration = std::time::Duration::from_secs(30);
+
+    /// Spawns a `Tail` script-var's command as a child process and forwards each
+    /// line of its stdout as a state update via `update_sender`. If the child dies,
+    /// the failure is logged and the process is restarted rather than propagated
+    /// as an error, since a flaky data source shouldn't take down eww. Restarts back
+    /// off exponentially (capped at `MAX_RESTART_BACKOFF`) and the thread gives up
+    /// after `MAX_EMPTY_RESTARTS` consecutive restarts that produced no output, so a
+    /// command that never emits a line doesn't busy-restart forever and leak the
+    /// thread past shutdown.
+    pub fn spawn_tail(name: VarName, command: String, update_sender: std::sync::mpsc::Sender<(VarName, PrimitiveValue)>) {
+        std::thread::spawn(move || {
+            let mut empty_restarts = 0;
+
+            loop {
+                let child = std::process::Command::new("/bin/sh")
+                    .arg("-c")
+                    .arg(&command)
+                    .stdout(std::process::Stdio::piped())
+                    .spawn();
+
+                let mut child = match child {
+                    std::result::Result::Ok(child) => child,
+                    std::result::Result::Err(err) => {
+                        log::error!("Failed to spawn tail script-var '{}': {}", name, err);
+                        std::thread::sleep(std::time::Duration::from_secs(1));
+                        continue;
+                    }
+                };
+
+                let mut produced_output = false;
+
+                if let Some(stdout) = child.stdout.take() {
+                    for line in std::io::BufRead::lines(std::io::BufReader::new(stdout)) {
+                        match line {
+                            std::result::Result::Ok(line) => {
+                                produced_output = true;
+                                if update_sender.send((name.clone(), PrimitiveValue::parse_string(&line))).is_err() {
+                                    return;
+                                }
+                            }
+                            std::result::Result::Err(err) => {
+                                log::warn!("Error reading tail script-var '{}' output: {}", name, err);
+                                break;
+                            }
+                        }
+                    }
+                }
+
+                let _ = child.wait();
+
+                empty_restarts = if produced_output { 0 } else { empty_restarts + 1 };
+                if empty_restarts >= Self::MAX_EMPTY_RESTARTS {
+                    log::error!(
+                        "tail script-var '{}' exited without producing any output {} times in a row, giving up",
+                        name,
+                        empty_restarts
+                    );
+                    return;
+                }
+
+                log::warn!("tail script-var '{}' process exited, restarting", name);
+                std::thread::sleep(std::cmp::min(
+                    std::time::Duration::from_millis(500) * 2u32.pow(empty_restarts),
+                    Self::MAX_RESTART_BACKOFF,
+                ));
+            }
+        });
+    }
+}
+
+/// The fully-qualified address of a `WidgetDefinition`: the `::`-separated chain of
+/// namespaces declared by the `<definitions namespace="...">` blocks it came from,
+/// plus its own name. A definition declared outside of any namespaced block has an
+/// empty `namespace` and lives in the global scope.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct WidgetPath {
+    pub namespace: Vec<String>,
+    pub name: String,
+}
+
+impl WidgetPath {
+    pub fn new(namespace: &[String], name: impl Into<String>) -> Self {
+        WidgetPath { namespace: namespace.to_vec(), name: name.into() }
+    }
+
+    fn parse_qualified(raw: &str) -> Self {
+        let mut segments: Vec<String> = raw.split("::").map(str::to_owned).collect();
+        let name = segments.pop().unwrap_or_default();
+        WidgetPath { namespace: segments, name }
+    }
+}
+
+impl fmt::Display for WidgetPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for segment in &self.namespace {
+            write!(f, "{}::", segment)?;
+        }
+        write!(f, "{}", self.name)
+    }
+}
+
+/// Looks up a `WidgetUse`'s name against a widget registry.
+///
+/// A `::`-qualified name (`weather::forecast`) always refers to a user-defined
+/// widget, so it's looked up directly and a miss is an error. An unqualified
+/// name is first searched from `current_namespace` outward to the global scope
+/// (most specific wins); if none of those scopes define it, we fall back to
+/// scanning every imported namespace, erroring if more than one of them defines
+/// the name. If exactly zero do, `Ok(None)` is returned rather than an error —
+/// an unqualified name that matches no definition anywhere is assumed to be one
+/// of eww's built-in widgets (`box`, `label`, ...), which this registry, scoped
+/// to user `<def>`s, has no visibility into.
+fn resolve_widget_path<'a>(
+    widgets: &'a HashMap<WidgetPath, WidgetDefinition>,
+    raw_name: &str,
+    current_namespace: &[String],
+) -> Result<Option<&'a WidgetDefinition>> {
+    if raw_name.contains("::") {
+        let path = WidgetPath::parse_qualified(raw_name);
+        return widgets.get(&path).map(Some).with_context(|| format!("no widget definition found for {}", path));
+    }
+
+    for depth in (0..=current_namespace.len()).rev() {
+        let path = WidgetPath::new(&current_namespace[..depth], raw_name);
+        if let Some(def) = widgets.get(&path) {
+            return Ok(Some(def));
+        }
+    }
+
+    let matches: Vec<_> = widgets.iter().filter(|(path, _)| path.name == raw_name).collect();
+    match matches.as_slice() {
+        [] => Ok(None),
+        [(_, def)] => Ok(Some(def)),
+        _ => bail!(
+            "ambiguous widget name '{}': matches definitions in namespaces {}",
+            raw_name,
+            matches.iter().map(|(path, _)| path.to_string()).collect::<Vec<_>>().join(", ")
+        ),
+    }
+}
+
+/// Recursively validates every name in a `WidgetUse` tree against `widgets`,
+/// resolving each one from `current_namespace` exactly as render-time lookups
+/// through `EwwConfig::resolve_widget` would. Called once per
+/// window and once per widget definition after all includes have been merged, so
+/// an unknown-qualified-reference or ambiguous widget-use anywhere in the tree —
+/// not just at a window's root — is caught at config-load time instead of at
+/// render time. Unqualified names that don't match any user `<def>` are assumed
+/// to be built-in widgets and aren't otherwise checked (see `resolve_widget_path`).
+fn validate_widget_use_tree(widgets: &HashMap<WidgetPath, WidgetDefinition>, widget_use: &WidgetUse, current_namespace: &[String]) -> Result<()> {
+    resolve_widget_path(widgets, &widget_use.name, current_namespace)
+        .with_context(|| format!("error resolving widget use '{}'", widget_use.name))?;
+    for child in &widget_use.children {
+        validate_widget_use_tree(widgets, child, current_namespace)?;
+    }
+    Ok(())
+}
+
+/// Rewrites an included library's own internal `::`-qualified references so they
+/// still resolve after `merge_included_config` reparents the library under
+/// `namespace_prefix`: a reference like `weather::forecast` that points at one of
+/// the library's own namespaces (`internal_namespaces`) becomes
+/// `<namespace_prefix>::weather::forecast`, matching where that definition was
+/// just moved to. References to namespaces the library doesn't define itself
+/// (i.e. ones it expects the *including* config to provide) are left untouched.
+fn rewrite_widget_use_namespace(widget_use: &mut WidgetUse, namespace_prefix: &[String], internal_namespaces: &HashSet<Vec<String>>) {
+    if widget_use.name.contains("::") {
+        let path = WidgetPath::parse_qualified(&widget_use.name);
+        if internal_namespaces.contains(&path.namespace) {
+            let rewritten = WidgetPath {
+                namespace: namespace_prefix.iter().cloned().chain(path.namespace).collect(),
+                name: path.name,
+            };
+            widget_use.name = rewritten.to_string();
+        }
+    }
+    for child in &mut widget_use.children {
+        rewrite_widget_use_namespace(child, namespace_prefix, internal_namespaces);
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct EwwConfig {
-    widgets: HashMap<String, WidgetDefinition>,
+    widgets: HashMap<WidgetPath, WidgetDefinition>,
     windows: HashMap<WindowName, EwwWindowDefinition>,
     initial_variables: HashMap<VarName, PrimitiveValue>,
     script_vars: Vec<ScriptVar>,
@@ -54,40 +275,156 @@ pub struct EwwConfig {
 
 impl EwwConfig {
     pub fn read_from_file<P: AsRef<std::path::Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let canonical_path = path
+            .canonicalize()
+            .with_context(|| format!("config file not found: {}", path.display()))?;
+        let mut visited_includes = HashSet::new();
+        visited_includes.insert(canonical_path.clone());
+        Self::read_from_file_resolving_includes(&canonical_path, &mut visited_includes)
+    }
+
+    fn read_from_file_resolving_includes(path: &Path, visited_includes: &mut HashSet<PathBuf>) -> Result<Self> {
         let content = std::fs::read_to_string(path)?;
-        let document = roxmltree::Document::parse(&content)?;
 
-        let result = EwwConfig::from_xml_element(XmlNode::from(document.root_element()).as_element()?.clone());
+        let xml_content = match ConfigFormat::from_path(path)? {
+            ConfigFormat::Xml => content,
+            format => {
+                let structured = format::parse_structured(format, &content)
+                    .with_context(|| format!("error parsing {}", path.display()))?;
+                format::structured_config_to_xml(&structured)
+            }
+        };
+
+        let document = roxmltree::Document::parse(&xml_content)?;
+        let base_dir = path.parent().unwrap_or_else(|| Path::new(".")).to_owned();
+
+        EwwConfig::from_xml_element(XmlNode::from(document.root_element()).as_element()?, &base_dir, visited_includes)
+    }
+
+    /// Resolves an `<include path="..."/>` node relative to `base_dir`, parsing the
+    /// referenced file recursively. `visited_includes` tracks the chain of absolute
+    /// paths currently being resolved (not every file ever included), so a file
+    /// reached along two separate branches (a diamond) isn't reported as a cycle,
+    /// while an include cycle is rejected with a clear error. A diamond's shared
+    /// file is merged once per branch, so its widgets and windows hit the duplicate
+    /// check in `merge_included_config` unless they land under different namespaces.
+    fn resolve_include(node: &XmlElement, base_dir: &Path, visited_includes: &mut HashSet<PathBuf>) -> Result<EwwConfig> {
+        let relative_path = node.attr("path")?;
+        let full_path = base_dir.join(relative_path);
+        let canonical_path = full_path
+            .canonicalize()
+            .with_context(|| format!("included file not found: {}", full_path.display()))?;
+
+        ensure!(
+            visited_includes.insert(canonical_path.clone()),
+            "cyclic include detected: {}",
+            canonical_path.display()
+        );
+        let result = EwwConfig::read_from_file_resolving_includes(&canonical_path, visited_includes)
+            .with_context(|| format!("error including {}", canonical_path.display()));
+        visited_includes.remove(&canonical_path);
         result
     }
 
-    pub fn from_xml_element(xml: XmlElement) -> Result<Self> {
-        let definitions = xml
-            .child("definitions")?
-            .child_elements()
-            .map(|child| {
-                let def = WidgetDefinition::from_xml_element(child)?;
-                Ok((def.name.clone(), def))
-            })
-            .collect::<Result<HashMap<_, _>>>()
-            .context("error parsing widget definitions")?;
-
-        let windows = xml
-            .child("windows")?
-            .child_elements()
-            .map(|child| {
-                Ok((
-                    WindowName(child.attr("name")?.to_owned()),
-                    EwwWindowDefinition::from_xml_element(child)?,
-                ))
-            })
-            .collect::<Result<HashMap<_, _>>>()
-            .context("error parsing window definitions")?;
+    /// Merges an included config's widgets, windows, variables and script-vars into
+    /// the accumulators of the config currently being parsed, erroring on any widget
+    /// or window name that's already defined rather than silently overwriting it.
+    /// Variables aren't checked that way: an included `var` replaces an earlier one
+    /// of the same name, and included script-vars are appended as they are.
+    ///
+    /// `namespace_prefix` is the namespace of the `<definitions>` block the
+    /// `<include>` appeared in (empty outside of one). It's prepended onto each
+    /// included widget's own namespace, so a library's global `clock` included into
+    /// a `<definitions namespace="x">` block lands at `x::clock` — namespacing the
+    /// *include site*, not just inline definitions. Everything included into the
+    /// same block shares that prefix, so a name declared by two of them is still
+    /// a duplicate.
+    ///
+    /// A `::`-qualified reference inside the included library's own widget/window
+    /// bodies that points at one of the library's own namespaces is rewritten onto
+    /// `namespace_prefix` too (via `rewrite_widget_use_namespace`), so a widget that
+    /// refers to a sibling as `weather::forecast` still resolves once that sibling
+    /// has been reparented to `<namespace_prefix>::weather::forecast`. A reference
+    /// is left alone if its namespace isn't one the library defines itself — it's
+    /// assumed to be a reference the including config is expected to satisfy.
+    fn merge_included_config(
+        widgets: &mut HashMap<WidgetPath, WidgetDefinition>,
+        windows: &mut HashMap<WindowName, EwwWindowDefinition>,
+        initial_variables: &mut HashMap<VarName, PrimitiveValue>,
+        script_vars: &mut Vec<ScriptVar>,
+        namespace_prefix: &[String],
+        included: EwwConfig,
+    ) -> Result<()> {
+        let internal_namespaces: HashSet<Vec<String>> = included.widgets.keys().map(|path| path.namespace.clone()).collect();
 
-        let variables_block = xml.child("variables").ok();
+        for (path, mut def) in included.widgets {
+            rewrite_widget_use_namespace(&mut def.widget, namespace_prefix, &internal_namespaces);
+            let path = WidgetPath {
+                namespace: namespace_prefix.iter().cloned().chain(path.namespace).collect(),
+                name: path.name,
+            };
+            ensure!(!widgets.contains_key(&path), "duplicate widget definition: {}", path);
+            widgets.insert(path, def);
+        }
+        for (name, mut window) in included.windows {
+            rewrite_widget_use_namespace(&mut window.widget, namespace_prefix, &internal_namespaces);
+            ensure!(!windows.contains_key(&name), "duplicate window definition: {}", name);
+            windows.insert(name, window);
+        }
+        initial_variables.extend(included.initial_variables);
+        script_vars.extend(included.script_vars);
+        Ok(())
+    }
 
+    pub fn from_xml_element(xml: XmlElement, base_dir: &Path, visited_includes: &mut HashSet<PathBuf>) -> Result<Self> {
+        let mut widgets = HashMap::new();
+        let mut windows = HashMap::new();
         let mut initial_variables = HashMap::new();
         let mut script_vars = Vec::new();
+
+        // `definitions` and `windows` are optional, like `variables` already was,
+        // so a library file included for just its widgets (or just its windows)
+        // doesn't need to carry empty `<windows/>`/`<definitions/>` stubs.
+        let definitions_node = xml.child("definitions").ok();
+        if let Some(definitions_node) = definitions_node {
+            let namespace: Vec<String> = definitions_node
+                .attr("namespace")
+                .ok()
+                .map(|namespace| namespace.split("::").map(str::to_owned).collect())
+                .unwrap_or_default();
+
+            for child in definitions_node.child_elements() {
+                if child.tag_name() == "include" {
+                    let included = Self::resolve_include(&child, base_dir, visited_includes)
+                        .context("error resolving <include> in definitions block")?;
+                    Self::merge_included_config(&mut widgets, &mut windows, &mut initial_variables, &mut script_vars, &namespace, included)?;
+                } else {
+                    let def = WidgetDefinition::from_xml_element(child)?;
+                    let path = WidgetPath::new(&namespace, def.name.clone());
+                    ensure!(!widgets.contains_key(&path), "duplicate widget definition: {}", path);
+                    widgets.insert(path, def);
+                }
+            }
+        }
+
+        let windows_node = xml.child("windows").ok();
+        if let Some(windows_node) = windows_node {
+            for child in windows_node.child_elements() {
+                if child.tag_name() == "include" {
+                    let included = Self::resolve_include(&child, base_dir, visited_includes)
+                        .context("error resolving <include> in windows block")?;
+                    Self::merge_included_config(&mut widgets, &mut windows, &mut initial_variables, &mut script_vars, &[], included)?;
+                } else {
+                    let name = WindowName(child.attr("name")?.to_owned());
+                    ensure!(!windows.contains_key(&name), "duplicate window definition: {}", name);
+                    windows.insert(name, EwwWindowDefinition::from_xml_element(child)?);
+                }
+            }
+        }
+
+        let variables_block = xml.child("variables").ok();
+
         if let Some(variables_block) = variables_block {
             for node in variables_block.child_elements() {
                 match node.tag_name() {
@@ -105,33 +442,82 @@ impl EwwConfig {
                     "script-var" => {
                         script_vars.push(ScriptVar::from_xml_element(node)?);
                     }
+                    "include" => {
+                        let included = Self::resolve_include(&node, base_dir, visited_includes)
+                            .context("error resolving <include> in variables block")?;
+                        Self::merge_included_config(&mut widgets, &mut windows, &mut initial_variables, &mut script_vars, &[], included)?;
+                    }
                     _ => bail!("Illegal element in variables block: {}", node.as_tag_string()),
                 }
             }
         }
 
+        // Validated here, once includes and namespace-prefix rewriting are fully
+        // resolved, rather than piecemeal while parsing: each definition's own body
+        // is checked against its own namespace, and each window's body against the
+        // global namespace, walking the whole widget-use tree rather than just its
+        // root (see `validate_widget_use_tree`).
+        for (path, def) in &widgets {
+            validate_widget_use_tree(&widgets, &def.widget, &path.namespace)
+                .with_context(|| format!("error validating widget definition '{}'", path))?;
+        }
+        for (name, window) in &windows {
+            validate_widget_use_tree(&widgets, &window.widget, &[]).with_context(|| format!("error validating window '{}'", name))?;
+        }
+
         Ok(EwwConfig {
-            widgets: definitions,
+            widgets,
             windows,
             initial_variables,
             script_vars,
         })
     }
 
+    /// Spawns every `Tail` script-var's child process, forwarding state updates
+    /// through `update_sender`. The caller (the state/event loop that owns the
+    /// receiving end) is expected to call this once at startup, right after
+    /// seeding state with `generate_initial_state`.
+    pub fn spawn_tail_vars(&self, update_sender: std::sync::mpsc::Sender<(VarName, PrimitiveValue)>) {
+        for var in &self.script_vars {
+            if let ScriptVar::Tail { name, command } = var {
+                ScriptVar::spawn_tail(name.clone(), command.clone(), update_sender.clone());
+            }
+        }
+    }
+
     // TODO this is kinda ugly
     pub fn generate_initial_state(&self) -> Result<HashMap<VarName, PrimitiveValue>> {
         let mut vars = self
             .script_vars
             .iter()
-            .map(|var| Ok((var.name.clone(), crate::eww_state::run_command(&var.command)?)))
+            .map(|var| {
+                let value = match var {
+                    ScriptVar::Poll { command, .. } => crate::eww_state::run_command(command)?,
+                    // The tail process hasn't produced a line yet; `spawn_tail_vars` will
+                    // push the real value in as soon as one arrives.
+                    ScriptVar::Tail { .. } => PrimitiveValue::parse_string(""),
+                };
+                Ok((var.name().clone(), value))
+            })
             .collect::<Result<HashMap<_, _>>>()?;
         vars.extend(self.get_default_vars().clone());
         Ok(vars)
     }
 
-    pub fn get_widgets(&self) -> &HashMap<String, WidgetDefinition> {
+    pub fn get_widgets(&self) -> &HashMap<WidgetPath, WidgetDefinition> {
         &self.widgets
     }
+
+    /// Resolves `raw_name` to a user-defined widget (see [`resolve_widget_path`]).
+    /// Unlike `resolve_widget_path` itself, a name that matches no definition
+    /// anywhere is an error here: callers of this method already know they're
+    /// resolving a *custom* widget-use (e.g. to expand it into its definition's
+    /// body), so "it's presumably a built-in" isn't a valid outcome for them.
+    pub fn resolve_widget(&self, raw_name: &str, current_namespace: &[String]) -> Result<&WidgetDefinition> {
+        resolve_widget_path(&self.widgets, raw_name, current_namespace)?
+            .with_context(|| format!("no widget definition found for '{}'", raw_name))
+    }
+
     pub fn get_windows(&self) -> &HashMap<WindowName, EwwWindowDefinition> {
         &self.windows
     }
@@ -169,6 +555,11 @@ pub struct EwwWindowDefinition {
 }
 
 impl EwwWindowDefinition {
+    /// Parses the window's size, position and widget-use tree without resolving the
+    /// tree's names; that tree isn't namespaced (a
+    /// window's body is always resolved in the global scope, see
+    /// `validate_widget_use_tree`'s caller in `EwwConfig::from_xml_element`), so
+    /// this doesn't need a widget registry in hand yet.
     pub fn from_xml_element(xml: XmlElement) -> Result<Self> {
         ensure_xml_tag_is!(xml, "window");
 
@@ -181,3 +572,154 @@ impl EwwWindowDefinition {
         Ok(EwwWindowDefinition { position, size, widget })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_temp_file(name: &str, content: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("eww-config-test-{}-{}", std::process::id(), name));
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    const EWW_NS: &str = r#"<eww><windows><window name="main"><size x="1" y="1"/><pos x="0" y="0"/><widget><box/></widget></window></windows></eww>"#;
+
+    #[test]
+    fn cyclic_include_is_rejected() {
+        let a = write_temp_file("cycle-a.xml", "<eww><windows></windows></eww>");
+        let b = write_temp_file("cycle-b.xml", &format!(r#"<eww><windows><include path="{}"/></windows></eww>"#, a.display()));
+        std::fs::write(&a, format!(r#"<eww><windows><include path="{}"/></windows></eww>"#, b.display())).unwrap();
+
+        let err = EwwConfig::read_from_file(&a).unwrap_err();
+        assert!(err.chain().any(|c| c.to_string().contains("cyclic include")));
+    }
+
+    #[test]
+    fn duplicate_window_definition_across_includes_errors() {
+        let lib = write_temp_file("dup-lib.xml", EWW_NS);
+        let main = write_temp_file(
+            "dup-main.xml",
+            &format!(
+                r#"<eww><windows><include path="{}"/><window name="main"><size x="1" y="1"/><pos x="0" y="0"/><widget><box/></widget></window></windows></eww>"#,
+                lib.display()
+            ),
+        );
+
+        let err = EwwConfig::read_from_file(&main).unwrap_err();
+        assert!(err.chain().any(|c| c.to_string().contains("duplicate window definition")));
+    }
+
+    fn parse_script_var(xml: &str) -> Result<ScriptVar> {
+        let document = roxmltree::Document::parse(xml)?;
+        ScriptVar::from_xml_element(XmlNode::from(document.root_element()).as_element()?)
+    }
+
+    #[test]
+    fn script_var_with_interval_is_poll_mode() {
+        let var = parse_script_var(r#"<script-var name="foo" interval="2s">echo 1</script-var>"#).unwrap();
+        assert!(matches!(var, ScriptVar::Poll { .. }));
+    }
+
+    #[test]
+    fn script_var_without_interval_is_tail_mode() {
+        let var = parse_script_var(r#"<script-var name="foo">tail -f /tmp/foo</script-var>"#).unwrap();
+        assert!(matches!(var, ScriptVar::Tail { .. }));
+    }
+
+    #[test]
+    fn script_var_type_tail_overrides_missing_interval_default() {
+        let var = parse_script_var(r#"<script-var name="foo" type="tail">tail -f /tmp/foo</script-var>"#).unwrap();
+        assert!(matches!(var, ScriptVar::Tail { .. }));
+    }
+
+    #[test]
+    fn script_var_unknown_type_errors() {
+        assert!(parse_script_var(r#"<script-var name="foo" type="bogus">echo 1</script-var>"#).is_err());
+    }
+
+    fn parse_config(xml: &str) -> Result<EwwConfig> {
+        let document = roxmltree::Document::parse(xml)?;
+        EwwConfig::from_xml_element(
+            XmlNode::from(document.root_element()).as_element()?,
+            Path::new("."),
+            &mut HashSet::new(),
+        )
+    }
+
+    #[test]
+    fn nested_widget_use_resolves_in_its_own_namespace() {
+        let xml = r#"<eww>
+            <definitions namespace="weather">
+                <def name="forecast"><label/></def>
+                <def name="panel"><box><forecast/></box></def>
+            </definitions>
+            <windows><window name="main"><size x="1" y="1"/><pos x="0" y="0"/><widget><use name="weather::panel"/></widget></window></windows>
+        </eww>"#;
+        assert!(parse_config(xml).is_ok());
+    }
+
+    #[test]
+    fn unqualified_unknown_widget_use_is_assumed_builtin() {
+        // "does-not-exist" matches no <def> anywhere, so it's treated as one of
+        // eww's built-in widgets (box, label, ...) rather than rejected.
+        let xml = r#"<eww>
+            <windows><window name="main"><size x="1" y="1"/><pos x="0" y="0"/><widget><does-not-exist/></widget></window></windows>
+        </eww>"#;
+        assert!(parse_config(xml).is_ok());
+    }
+
+    #[test]
+    fn qualified_unknown_widget_use_is_rejected() {
+        let xml = r#"<eww>
+            <definitions namespace="weather">
+                <def name="panel"><box><use name="weather::does-not-exist"/></box></def>
+            </definitions>
+            <windows><window name="main"><size x="1" y="1"/><pos x="0" y="0"/><widget><use name="weather::panel"/></widget></window></windows>
+        </eww>"#;
+        let err = parse_config(xml).unwrap_err();
+        assert!(err.chain().any(|c| c.to_string().contains("no widget definition found")));
+    }
+
+    #[test]
+    fn ambiguous_widget_use_across_namespaces_is_rejected() {
+        // Two libraries, each declaring their own namespaced `clock`, included
+        // side by side — `<definitions>` itself only ever declares one namespace
+        // per block, so importing two namespaces means including two files.
+        let lib_a = write_temp_file("ns-a.xml", r#"<eww><definitions namespace="a"><def name="clock"><label/></def></definitions></eww>"#);
+        let lib_b = write_temp_file("ns-b.xml", r#"<eww><definitions namespace="b"><def name="clock"><label/></def></definitions></eww>"#);
+        let main = write_temp_file(
+            "ns-main.xml",
+            &format!(
+                r#"<eww>
+                    <definitions><include path="{}"/><include path="{}"/></definitions>
+                    <windows><window name="main"><size x="1" y="1"/><pos x="0" y="0"/><widget><clock/></widget></window></windows>
+                </eww>"#,
+                lib_a.display(),
+                lib_b.display()
+            ),
+        );
+
+        let err = EwwConfig::read_from_file(&main).unwrap_err();
+        assert!(err.chain().any(|c| c.to_string().contains("ambiguous widget name")));
+    }
+
+    #[test]
+    fn rewrite_widget_use_namespace_reparents_internal_references() {
+        let mut widget_use = WidgetUse { name: "weather::forecast".to_owned(), children: vec![] };
+        let mut internal_namespaces = HashSet::new();
+        internal_namespaces.insert(vec!["weather".to_owned()]);
+
+        rewrite_widget_use_namespace(&mut widget_use, &["lib".to_owned()], &internal_namespaces);
+        assert_eq!(widget_use.name, "lib::weather::forecast");
+    }
+
+    #[test]
+    fn rewrite_widget_use_namespace_leaves_external_references_untouched() {
+        let mut widget_use = WidgetUse { name: "host::theme".to_owned(), children: vec![] };
+        let internal_namespaces = HashSet::new();
+
+        rewrite_widget_use_namespace(&mut widget_use, &["lib".to_owned()], &internal_namespaces);
+        assert_eq!(widget_use.name, "host::theme");
+    }
+}
@@ -0,0 +1,115 @@
+use anyhow::*;
+
+/// A thin, owned-free wrapper around a `roxmltree::Node` that may be either an
+/// element or text, mirroring the shape `EwwConfig`'s parsers expect: "give me
+/// the element/text this node is, or a source-position-annotated error".
+#[derive(Clone, Copy)]
+pub struct XmlNode<'a> {
+    node: roxmltree::Node<'a, 'a>,
+}
+
+impl<'a> From<roxmltree::Node<'a, 'a>> for XmlNode<'a> {
+    fn from(node: roxmltree::Node<'a, 'a>) -> Self {
+        XmlNode { node }
+    }
+}
+
+impl<'a> XmlNode<'a> {
+    pub fn as_element(&self) -> Result<XmlElement<'a>> {
+        ensure!(
+            self.node.is_element(),
+            "{} | expected an element, found {}",
+            self.text_pos(),
+            self.as_tag_string()
+        );
+        Ok(XmlElement { node: self.node })
+    }
+
+    pub fn as_text(&self) -> Result<XmlText<'a>> {
+        ensure!(
+            self.node.is_text(),
+            "{} | expected text, found {}",
+            self.text_pos(),
+            self.as_tag_string()
+        );
+        Ok(XmlText { node: self.node })
+    }
+
+    /// Like `as_text`, but also accepts an element node by falling back to its
+    /// raw source slice, for places (like a `var`'s default value) that allow
+    /// either a plain text value or inline markup to be used verbatim.
+    pub fn as_text_or_sourcecode(&self) -> String {
+        if let std::result::Result::Ok(text) = self.as_text() {
+            return text.text();
+        }
+        self.node.document().input_text()[self.node.range()].to_owned()
+    }
+
+    pub fn text_pos(&self) -> roxmltree::TextPos {
+        self.node.document().text_pos_at(self.node.range().start)
+    }
+
+    pub fn as_tag_string(&self) -> String {
+        if self.node.is_element() {
+            format!("<{}>", self.node.tag_name().name())
+        } else {
+            "[text]".to_owned()
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct XmlElement<'a> {
+    node: roxmltree::Node<'a, 'a>,
+}
+
+impl<'a> XmlElement<'a> {
+    pub fn tag_name(&self) -> &'a str {
+        self.node.tag_name().name()
+    }
+
+    pub fn text_pos(&self) -> roxmltree::TextPos {
+        self.node.document().text_pos_at(self.node.range().start)
+    }
+
+    pub fn as_tag_string(&self) -> String {
+        format!("<{}>", self.tag_name())
+    }
+
+    pub fn attr(&self, name: &str) -> Result<&'a str> {
+        self.node
+            .attribute(name)
+            .with_context(|| format!("{} | missing required attribute '{}' on {}", self.text_pos(), name, self.as_tag_string()))
+    }
+
+    pub fn child(&self, name: &str) -> Result<XmlElement<'a>> {
+        self.node
+            .children()
+            .find(|child| child.is_element() && child.tag_name().name() == name)
+            .map(|node| XmlElement { node })
+            .with_context(|| format!("{} | missing required child <{}> in {}", self.text_pos(), name, self.as_tag_string()))
+    }
+
+    pub fn child_elements(&self) -> impl Iterator<Item = XmlElement<'a>> {
+        self.node.children().filter(|child| child.is_element()).map(|node| XmlElement { node })
+    }
+
+    pub fn only_child(&self) -> Result<XmlNode<'a>> {
+        self.node
+            .children()
+            .find(|child| child.is_element() || child.is_text())
+            .map(|node| XmlNode { node })
+            .with_context(|| format!("{} | expected a single child in {}", self.text_pos(), self.as_tag_string()))
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct XmlText<'a> {
+    node: roxmltree::Node<'a, 'a>,
+}
+
+impl<'a> XmlText<'a> {
+    pub fn text(&self) -> String {
+        self.node.text().unwrap_or_default().to_owned()
+    }
+}